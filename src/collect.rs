@@ -0,0 +1,138 @@
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use glob::Pattern;
+
+/// Recursively walks `roots`, keeping files that match at least one
+/// `includes` glob (or every file when `includes` is empty) and none of the
+/// `excludes` globs.
+///
+/// Directory entries are sorted before recursing, so the returned list is
+/// deterministic regardless of filesystem iteration order — callers can rely
+/// on stable ordering even though the files themselves are later processed
+/// concurrently.
+pub fn collect_paths<P: AsRef<Path>>(
+    roots: &[P],
+    includes: &[String],
+    excludes: &[String],
+) -> io::Result<Vec<PathBuf>> {
+    let includes = compile_patterns(includes);
+    let excludes = compile_patterns(excludes);
+
+    let mut collected = Vec::new();
+    let mut visited_dirs = HashSet::new();
+    for root in roots {
+        walk(
+            root.as_ref(),
+            &includes,
+            &excludes,
+            &mut visited_dirs,
+            &mut collected,
+        )?;
+    }
+    collected.sort();
+    Ok(collected)
+}
+
+fn compile_patterns(patterns: &[String]) -> Vec<Pattern> {
+    patterns
+        .iter()
+        .filter_map(|pattern| Pattern::new(pattern).ok())
+        .collect()
+}
+
+fn walk(
+    path: &Path,
+    includes: &[Pattern],
+    excludes: &[Pattern],
+    visited_dirs: &mut HashSet<PathBuf>,
+    collected: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    let metadata = std::fs::symlink_metadata(path)?;
+
+    if metadata.is_dir() || (metadata.is_symlink() && path.is_dir()) {
+        // Canonicalize before recursing and remember every directory visited
+        // so a symlink cycle (or two symlinks aliasing the same directory)
+        // terminates instead of recursing forever.
+        let canonical = std::fs::canonicalize(path)?;
+        if !visited_dirs.insert(canonical) {
+            return Ok(());
+        }
+
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(path)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .collect();
+        entries.sort();
+
+        for entry in entries {
+            walk(&entry, includes, excludes, visited_dirs, collected)?;
+        }
+    } else if is_selected(path, includes, excludes) {
+        collected.push(path.to_path_buf());
+    }
+
+    Ok(())
+}
+
+fn is_selected(path: &Path, includes: &[Pattern], excludes: &[Pattern]) -> bool {
+    let path = path.to_string_lossy();
+
+    if excludes.iter().any(|pattern| pattern.matches(&path)) {
+        return false;
+    }
+
+    includes.is_empty() || includes.iter().any(|pattern| pattern.matches(&path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_all_files_sorted_when_no_filters_given() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("b.txt"), "").unwrap();
+        std::fs::write(dir.path().join("a.log"), "").unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("sub/c.txt"), "").unwrap();
+
+        let collected = collect_paths(&[dir.path()], &[], &[]).unwrap();
+
+        assert_eq!(collected.len(), 3);
+        assert!(collected.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn applies_include_and_exclude_globs() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("keep.txt"), "").unwrap();
+        std::fs::write(dir.path().join("skip.txt"), "").unwrap();
+        std::fs::write(dir.path().join("skip.log"), "").unwrap();
+
+        let includes = vec![format!("{}/*.txt", dir.path().display())];
+        let excludes = vec![format!("{}/skip.*", dir.path().display())];
+
+        let collected = collect_paths(&[dir.path()], &includes, &excludes).unwrap();
+
+        assert_eq!(collected, vec![dir.path().join("keep.txt")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn terminates_on_a_symlink_cycle() {
+        let dir = tempfile::tempdir().unwrap();
+        let sub = dir.path().join("sub");
+        std::fs::create_dir(&sub).unwrap();
+        std::fs::write(sub.join("file.txt"), "").unwrap();
+
+        // sub/loop -> dir, so walking sub/loop/sub/loop/... would recurse
+        // forever without the visited-directories guard.
+        std::os::unix::fs::symlink(dir.path(), sub.join("loop")).unwrap();
+
+        let collected = collect_paths(&[dir.path()], &[], &[]).unwrap();
+
+        assert_eq!(collected, vec![sub.join("file.txt")]);
+    }
+}
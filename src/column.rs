@@ -0,0 +1,225 @@
+use std::cmp::Ordering;
+use std::io::{self, Write};
+
+use crate::BlockHandler;
+
+/// How a single column should be compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareKind {
+    /// Plain lexical (byte) ordering.
+    Lexical,
+    /// Parse the field as `f64`, falling back to lexical ordering if it
+    /// doesn't parse, so malformed rows still sort deterministically.
+    Numeric,
+    /// Lexical ordering ignoring ASCII/Unicode case.
+    CaseInsensitive,
+}
+
+/// One key in an ordered sort spec: which column, how to compare it, and
+/// whether to reverse that comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompareKey {
+    pub column: usize,
+    pub kind: CompareKind,
+    pub reverse: bool,
+}
+
+impl CompareKey {
+    /// Parses a single comparison spec such as `2n` (column 2, numeric),
+    /// `1` (column 1, lexical), `3i` (column 3, case-insensitive), or `2nr`
+    /// (column 2, numeric, reversed). Columns are 1-indexed in the spec and
+    /// stored 0-indexed.
+    pub fn parse(spec: &str) -> Option<Self> {
+        let digit_count = spec.chars().take_while(|c| c.is_ascii_digit()).count();
+        let (digits, suffix) = spec.split_at(digit_count);
+        let column: usize = digits.parse().ok()?;
+        if column == 0 {
+            return None;
+        }
+
+        let mut kind = CompareKind::Lexical;
+        let mut reverse = false;
+        for flag in suffix.chars() {
+            match flag {
+                'n' => kind = CompareKind::Numeric,
+                'i' => kind = CompareKind::CaseInsensitive,
+                'r' => reverse = true,
+                _ => return None,
+            }
+        }
+
+        Some(CompareKey {
+            column: column - 1,
+            kind,
+            reverse,
+        })
+    }
+}
+
+/// Parses a full comparison spec like `2n,1` into an ordered list of keys,
+/// skipping any part that doesn't parse.
+pub fn parse_compare_spec(spec: &str) -> Vec<CompareKey> {
+    spec.split(',')
+        .filter_map(|part| CompareKey::parse(part.trim()))
+        .collect()
+}
+
+fn field<'a>(fields: &[&'a str], column: usize) -> &'a str {
+    fields.get(column).copied().unwrap_or("")
+}
+
+fn compare_rows(keys: &[CompareKey], a: &[&str], b: &[&str]) -> Ordering {
+    for key in keys {
+        let lhs = field(a, key.column);
+        let rhs = field(b, key.column);
+
+        let ordering = match key.kind {
+            CompareKind::Lexical => lhs.cmp(rhs),
+            CompareKind::CaseInsensitive => lhs.to_lowercase().cmp(&rhs.to_lowercase()),
+            CompareKind::Numeric => match (lhs.parse::<f64>(), rhs.parse::<f64>()) {
+                (Ok(l), Ok(r)) => l.partial_cmp(&r).unwrap_or(Ordering::Equal),
+                _ => lhs.cmp(rhs),
+            },
+        };
+
+        if ordering != Ordering::Equal {
+            return if key.reverse { ordering.reverse() } else { ordering };
+        }
+    }
+
+    Ordering::Equal
+}
+
+/// A `BlockHandler` for delimiter-separated central blocks: selects and
+/// reorders a subset of columns by index, and stable-sorts rows by one or
+/// more typed key columns before printing the result.
+pub struct ColumnHandler {
+    delimiter: char,
+    columns: Option<Vec<usize>>,
+    sort_keys: Vec<CompareKey>,
+}
+
+impl ColumnHandler {
+    pub fn new() -> Self {
+        Self {
+            delimiter: '\t',
+            columns: None,
+            sort_keys: Vec::new(),
+        }
+    }
+
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Restricts and reorders output to these 0-indexed columns.
+    pub fn with_columns(mut self, columns: Vec<usize>) -> Self {
+        self.columns = Some(columns);
+        self
+    }
+
+    /// Sets the sort order from a spec like `2n,1` (see `CompareKey::parse`).
+    pub fn with_sort(mut self, spec: &str) -> Self {
+        self.sort_keys = parse_compare_spec(spec);
+        self
+    }
+}
+
+impl Default for ColumnHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BlockHandler for ColumnHandler {
+    fn handle(&self, content: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if content.is_empty() {
+            return Ok(());
+        }
+
+        let mut rows: Vec<Vec<&str>> = content
+            .lines()
+            .map(|line| line.split(self.delimiter).collect())
+            .collect();
+
+        if !self.sort_keys.is_empty() {
+            rows.sort_by(|a, b| compare_rows(&self.sort_keys, a, b));
+        }
+
+        let mut stdout = io::stdout().lock();
+        for row in &rows {
+            let selected: Vec<&str> = match &self.columns {
+                Some(columns) => columns.iter().map(|&i| field(row, i)).collect(),
+                None => row.clone(),
+            };
+            writeln!(stdout, "{}", selected.join(&self.delimiter.to_string()))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_column_kind_and_reverse_flags() {
+        assert_eq!(
+            CompareKey::parse("2n").unwrap(),
+            CompareKey {
+                column: 1,
+                kind: CompareKind::Numeric,
+                reverse: false,
+            }
+        );
+        assert_eq!(
+            CompareKey::parse("3ir").unwrap(),
+            CompareKey {
+                column: 2,
+                kind: CompareKind::CaseInsensitive,
+                reverse: true,
+            }
+        );
+        assert_eq!(
+            CompareKey::parse("1").unwrap(),
+            CompareKey {
+                column: 0,
+                kind: CompareKind::Lexical,
+                reverse: false,
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_specs() {
+        assert!(CompareKey::parse("0").is_none());
+        assert!(CompareKey::parse("2x").is_none());
+        assert!(CompareKey::parse("n").is_none());
+    }
+
+    #[test]
+    fn parse_compare_spec_skips_unparseable_parts() {
+        let keys = parse_compare_spec("2n, garbage ,1");
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[0].column, 1);
+        assert_eq!(keys[1].column, 0);
+    }
+
+    #[test]
+    fn compare_rows_sorts_numerically_not_lexically() {
+        let keys = parse_compare_spec("1n");
+        let mut rows: Vec<Vec<&str>> = vec![vec!["10"], vec!["2"], vec!["1"]];
+        rows.sort_by(|a, b| compare_rows(&keys, a, b));
+        assert_eq!(rows, vec![vec!["1"], vec!["2"], vec!["10"]]);
+    }
+
+    #[test]
+    fn compare_rows_reverses_when_requested() {
+        let keys = parse_compare_spec("1nr");
+        let mut rows: Vec<Vec<&str>> = vec![vec!["1"], vec!["2"], vec!["10"]];
+        rows.sort_by(|a, b| compare_rows(&keys, a, b));
+        assert_eq!(rows, vec![vec!["10"], vec!["2"], vec!["1"]]);
+    }
+}
@@ -0,0 +1,64 @@
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Errors produced while processing a single file.
+#[derive(Debug)]
+pub enum ProcessError {
+    /// The input file could not be opened.
+    Open { path: PathBuf, source: io::Error },
+    /// A line could not be read (e.g. it wasn't valid UTF-8).
+    Read { line: usize, source: io::Error },
+    /// A handler returned an error while processing a block.
+    Handler(Box<dyn StdError>),
+    /// The rewritten output could not be written back out.
+    Write { path: PathBuf, source: io::Error },
+}
+
+impl fmt::Display for ProcessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProcessError::Open { path, source } => {
+                write!(f, "failed to open {}: {source}", path.display())
+            }
+            ProcessError::Read { line, source } => {
+                write!(f, "failed to read line {line}: {source}")
+            }
+            ProcessError::Handler(source) => write!(f, "handler error: {source}"),
+            ProcessError::Write { path, source } => {
+                write!(f, "failed to write {}: {source}", path.display())
+            }
+        }
+    }
+}
+
+impl StdError for ProcessError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            ProcessError::Open { source, .. } => Some(source),
+            ProcessError::Read { source, .. } => Some(source),
+            ProcessError::Handler(source) => Some(source.as_ref()),
+            ProcessError::Write { source, .. } => Some(source),
+        }
+    }
+}
+
+/// Returns true if `err` is (or wraps) an `io::Error` with `ErrorKind::BrokenPipe`.
+///
+/// Handlers that write to stdout surface a broken downstream pipe (e.g. output
+/// piped into `head`) this way; callers should treat it as a clean early stop
+/// rather than a real failure.
+pub fn is_broken_pipe(err: &(dyn StdError + 'static)) -> bool {
+    let mut current = Some(err);
+    while let Some(err) = current {
+        if err
+            .downcast_ref::<io::Error>()
+            .is_some_and(|err| err.kind() == io::ErrorKind::BrokenPipe)
+        {
+            return true;
+        }
+        current = err.source();
+    }
+    false
+}
@@ -0,0 +1,54 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek};
+use std::path::Path;
+
+use flate2::read::MultiGzDecoder;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Opens `path` and returns a `BufRead` over its decompressed contents.
+///
+/// Detects gzip input either by a `.gz` extension or by sniffing the leading
+/// magic bytes, so callers don't need to know up front whether a file is
+/// compressed. Concatenated gzip members (multiple streams appended to the
+/// same file) are transparently drained in full via `MultiGzDecoder`, rather
+/// than stopping after the first member's trailer.
+pub fn open_input<P: AsRef<Path>>(path: P) -> io::Result<Box<dyn BufRead>> {
+    let path = path.as_ref();
+    let mut file = File::open(path)?;
+
+    if looks_like_gzip(path, &mut file)? {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(file))))
+    } else {
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+fn looks_like_gzip(path: &Path, file: &mut File) -> io::Result<bool> {
+    if path.extension().and_then(|ext| ext.to_str()) == Some("gz") {
+        return Ok(true);
+    }
+
+    let mut magic = [0u8; 2];
+    match file.read_exact(&mut magic) {
+        Ok(()) => {
+            file.rewind()?;
+            Ok(magic == GZIP_MAGIC)
+        }
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+            file.rewind()?;
+            Ok(false)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Reports whether `path` would be transparently decompressed by
+/// `open_input` (same detection: `.gz` extension or leading gzip magic
+/// bytes) — used by callers that need to know up front whether writing
+/// plain text back over `path` would corrupt a compressed source.
+pub fn is_compressed<P: AsRef<Path>>(path: P) -> io::Result<bool> {
+    let path = path.as_ref();
+    let mut file = File::open(path)?;
+    looks_like_gzip(path, &mut file)
+}
@@ -1,6 +1,26 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{self, BufRead, Write};
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::channel;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use notify::{RecursiveMode, Watcher};
+
+mod collect;
+mod column;
+mod error;
+mod input;
+mod sentinel;
+mod transform;
+
+use collect::collect_paths;
+use column::ColumnHandler;
+use error::{is_broken_pipe, ProcessError};
+use input::{is_compressed, open_input};
+use sentinel::{from_specs, Sentinel};
+use transform::{write_atomically, BlockTransform, UppercaseTransform};
 
 #[derive(Debug, Clone, PartialEq)]
 enum ParserState {
@@ -10,7 +30,7 @@ enum ParserState {
     Finished,
 }
 
-trait BlockHandler {
+pub(crate) trait BlockHandler: Send + Sync {
     fn handle(&self, content: &str) -> Result<(), Box<dyn std::error::Error>>;
 }
 
@@ -29,9 +49,10 @@ impl PrintHandler {
 impl BlockHandler for PrintHandler {
     fn handle(&self, content: &str) -> Result<(), Box<dyn std::error::Error>> {
         if !content.is_empty() {
-            println!("=== Start: {} ===", self.label);
-            println!("{}", content);
-            println!("===  End: {}  ===", self.label);
+            let mut stdout = io::stdout().lock();
+            writeln!(stdout, "=== Start: {} ===", self.label)?;
+            writeln!(stdout, "{}", content)?;
+            writeln!(stdout, "===  End: {}  ===", self.label)?;
         }
         Ok(())
     }
@@ -40,17 +61,36 @@ impl BlockHandler for PrintHandler {
 struct FileParser {
     state: ParserState,
     block_content: String,
-    pre_sentinel: String,
-    post_sentinel: String,
+    pre_sentinel: Sentinel,
+    post_sentinel: Sentinel,
 }
 
 impl FileParser {
-    fn new(pre_sentinel: &str, post_sentinel: &str) -> Self {
+    fn new(pre_sentinel: impl Into<Sentinel>, post_sentinel: impl Into<Sentinel>) -> Self {
         Self {
             state: ParserState::PreBlock,
             block_content: String::new(),
-            pre_sentinel: pre_sentinel.to_string(),
-            post_sentinel: post_sentinel.to_string(),
+            pre_sentinel: pre_sentinel.into(),
+            post_sentinel: post_sentinel.into(),
+        }
+    }
+
+    /// Runs a handler result through the broken-pipe check, wrapping a real
+    /// failure as a `ProcessError::Handler`. Returns `true` when the handler
+    /// hit a broken downstream pipe (e.g. output piped into `head`), in which
+    /// case the caller should stop feeding it further lines; a clean stop
+    /// also marks the parser `Finished`.
+    fn invoke_handler(
+        &mut self,
+        result: Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<bool, ProcessError> {
+        match result {
+            Ok(()) => Ok(false),
+            Err(err) if is_broken_pipe(err.as_ref()) => {
+                self.state = ParserState::Finished;
+                Ok(true)
+            }
+            Err(err) => Err(ProcessError::Handler(err)),
         }
     }
 
@@ -58,15 +98,19 @@ impl FileParser {
         &mut self,
         line: &str,
         processor: &FileProcessor,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<(), ProcessError> {
         match self.state {
             ParserState::PreBlock => {
-                if line.trim() == self.pre_sentinel {
-                    processor.pre_handler.handle(&self.block_content)?;
+                if self.pre_sentinel.is_match(line) {
+                    if self.invoke_handler(processor.pre_handler.handle(&self.block_content))? {
+                        return Ok(());
+                    }
                     self.block_content.clear();
                     self.state = ParserState::CentralBlock;
-                } else if line.trim() == self.post_sentinel {
-                    processor.pre_handler.handle(&self.block_content)?;
+                } else if self.post_sentinel.is_match(line) {
+                    if self.invoke_handler(processor.pre_handler.handle(&self.block_content))? {
+                        return Ok(());
+                    }
                     self.block_content.clear();
                     self.state = ParserState::PostBlock;
                 } else {
@@ -77,9 +121,11 @@ impl FileParser {
                 }
             }
             ParserState::CentralBlock => {
-                if line.trim() == self.post_sentinel {
+                if self.post_sentinel.is_match(line) {
                     if let Some(ref handler) = processor.central_handler {
-                        handler.handle(&self.block_content)?;
+                        if self.invoke_handler(handler.handle(&self.block_content))? {
+                            return Ok(());
+                        }
                     }
                     self.block_content.clear();
                     self.state = ParserState::PostBlock;
@@ -103,19 +149,23 @@ impl FileParser {
         Ok(())
     }
 
-    fn finish(&mut self, processor: &FileProcessor) -> Result<(), Box<dyn std::error::Error>> {
+    fn is_finished(&self) -> bool {
+        self.state == ParserState::Finished
+    }
+
+    fn finish(&mut self, processor: &FileProcessor) -> Result<(), ProcessError> {
         match self.state {
             ParserState::PreBlock => {
-                processor.pre_handler.handle(&self.block_content)?;
+                self.invoke_handler(processor.pre_handler.handle(&self.block_content))?;
             }
             ParserState::CentralBlock => {
                 if let Some(ref handler) = processor.central_handler {
-                    handler.handle(&self.block_content)?;
+                    self.invoke_handler(handler.handle(&self.block_content))?;
                 }
             }
             ParserState::PostBlock => {
                 if let Some(ref handler) = processor.post_handler {
-                    handler.handle(&self.block_content)?;
+                    self.invoke_handler(handler.handle(&self.block_content))?;
                 }
             }
             ParserState::Finished => {
@@ -128,47 +178,548 @@ impl FileParser {
 }
 
 struct FileProcessor {
-    pre_handler: Box<dyn BlockHandler>,
-    central_handler: Option<Box<dyn BlockHandler>>,
-    post_handler: Option<Box<dyn BlockHandler>>,
+    pre_handler: Arc<dyn BlockHandler>,
+    central_handler: Option<Arc<dyn BlockHandler>>,
+    post_handler: Option<Arc<dyn BlockHandler>>,
+    pre_transform: Option<Arc<dyn BlockTransform>>,
+    central_transform: Option<Arc<dyn BlockTransform>>,
+    post_transform: Option<Arc<dyn BlockTransform>>,
 }
 
 impl FileProcessor {
     fn new() -> Self {
         Self {
-            pre_handler: Box::new(PrintHandler::new("PRE-BLOCK")),
-            central_handler: Some(Box::new(PrintHandler::new("CENTRAL-BLOCK"))),
-            post_handler: Some(Box::new(PrintHandler::new("POST-BLOCK"))),
+            pre_handler: Arc::new(PrintHandler::new("PRE-BLOCK")),
+            central_handler: Some(Arc::new(PrintHandler::new("CENTRAL-BLOCK"))),
+            post_handler: Some(Arc::new(PrintHandler::new("POST-BLOCK"))),
+            pre_transform: None,
+            central_transform: None,
+            post_transform: None,
         }
     }
 
+    fn with_pre_transform(mut self, transform: impl BlockTransform + 'static) -> Self {
+        self.pre_transform = Some(Arc::new(transform));
+        self
+    }
+
+    fn with_central_transform(mut self, transform: impl BlockTransform + 'static) -> Self {
+        self.central_transform = Some(Arc::new(transform));
+        self
+    }
+
+    fn with_post_transform(mut self, transform: impl BlockTransform + 'static) -> Self {
+        self.post_transform = Some(Arc::new(transform));
+        self
+    }
+
+    /// Replaces the central-block handler, e.g. to install a `ColumnHandler`
+    /// in place of the default `PrintHandler`.
+    fn with_central_handler(mut self, handler: impl BlockHandler + 'static) -> Self {
+        self.central_handler = Some(Arc::new(handler));
+        self
+    }
+
     fn process_file<P: AsRef<Path>>(
         &self,
         path: P,
-        pre_sentinel: &str,
-        post_sentinel: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let file = File::open(path).expect("failed to open file");
-        let reader = BufReader::new(file);
+        pre_sentinel: impl Into<Sentinel>,
+        post_sentinel: impl Into<Sentinel>,
+    ) -> Result<(), ProcessError> {
+        let path = path.as_ref();
+        let reader = open_input(path).map_err(|source| ProcessError::Open {
+            path: path.to_path_buf(),
+            source,
+        })?;
 
         let mut parser = FileParser::new(pre_sentinel, post_sentinel);
 
-        for line in reader.lines() {
-            let line = line.expect("failed to read line");
+        for (line_no, line) in reader.lines().enumerate() {
+            let line = line.map_err(|source| ProcessError::Read {
+                line: line_no + 1,
+                source,
+            })?;
             parser.process_line(&line, self)?;
+
+            if parser.is_finished() {
+                // A handler hit a broken pipe: stop reading (and decoding —
+                // this might be a large gzip stream) the rest of the input
+                // rather than draining it just to discard every line.
+                return Ok(());
+            }
         }
 
         parser.finish(self)?;
 
         Ok(())
     }
+
+    /// Recursively collects files under `roots` matching `includes`/`excludes`
+    /// glob sets, then processes them concurrently across a small worker
+    /// pool — point this at a directory tree to process thousands of files
+    /// in parallel.
+    ///
+    /// Collection happens up front and is sorted, so which files get
+    /// processed is deterministic even though the order in which they
+    /// finish (and interleave their output) is not. Each file gets its own
+    /// `FileParser`, so per-file block state never leaks across threads.
+    ///
+    /// Returns the number of files that failed to process; per-file errors
+    /// are logged to stderr rather than aborting the batch.
+    fn process_paths<P: AsRef<Path>>(
+        &self,
+        roots: &[P],
+        includes: &[String],
+        excludes: &[String],
+        pre_sentinel: impl Into<Sentinel>,
+        post_sentinel: impl Into<Sentinel>,
+    ) -> io::Result<usize> {
+        let paths = collect_paths(roots, includes, excludes)?;
+        let pre_sentinel = pre_sentinel.into();
+        let post_sentinel = post_sentinel.into();
+
+        let worker_count = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .min(paths.len().max(1));
+
+        let jobs = Mutex::new(paths.into_iter());
+        let failures = AtomicUsize::new(0);
+
+        thread::scope(|scope| {
+            for _ in 0..worker_count {
+                let jobs = &jobs;
+                let failures = &failures;
+                let pre_sentinel = pre_sentinel.clone();
+                let post_sentinel = post_sentinel.clone();
+
+                scope.spawn(move || loop {
+                    let next = jobs.lock().unwrap().next();
+                    let Some(path) = next else { break };
+
+                    if let Err(err) =
+                        self.process_file(&path, pre_sentinel.clone(), post_sentinel.clone())
+                    {
+                        eprintln!("error processing {}: {err}", path.display());
+                        failures.fetch_add(1, Ordering::Relaxed);
+                    }
+                });
+            }
+        });
+
+        Ok(failures.load(Ordering::Relaxed))
+    }
+
+    /// Reassembles the pre/central/post blocks of `path` — running each
+    /// through its configured `BlockTransform` — and writes the result to
+    /// `output`, or atomically back over `path` itself when `output` is
+    /// `None`. Sentinel lines are copied through verbatim so the rewritten
+    /// file keeps whatever delimiter text was actually present.
+    ///
+    /// `open_input` reads compressed sources transparently, but the
+    /// reassembled output is always plain text — writing that back in place
+    /// over a compressed `path` would silently replace it with an
+    /// unreadable (uncompressed-but-`.gz`-named) file, so in-place rewrite
+    /// of a compressed source is refused; pass `output` to write a
+    /// decompressed copy elsewhere instead.
+    fn rewrite_file<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        path: P,
+        output: Option<Q>,
+        pre_sentinel: impl Into<Sentinel>,
+        post_sentinel: impl Into<Sentinel>,
+    ) -> Result<(), ProcessError> {
+        let path = path.as_ref();
+        let pre_sentinel = pre_sentinel.into();
+        let post_sentinel = post_sentinel.into();
+
+        if output.is_none() && is_compressed(path).unwrap_or(false) {
+            return Err(ProcessError::Write {
+                path: path.to_path_buf(),
+                source: io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "refusing to rewrite compressed input in place; pass --output to write a decompressed copy instead",
+                ),
+            });
+        }
+
+        let reader = open_input(path).map_err(|source| ProcessError::Open {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        let mut state = ParserState::PreBlock;
+        let mut pre_lines = Vec::new();
+        let mut pre_marker = None;
+        let mut central_lines = Vec::new();
+        let mut post_marker = None;
+        let mut post_lines = Vec::new();
+
+        for (line_no, line) in reader.lines().enumerate() {
+            let line = line.map_err(|source| ProcessError::Read {
+                line: line_no + 1,
+                source,
+            })?;
+
+            match state {
+                ParserState::PreBlock => {
+                    if pre_sentinel.is_match(&line) {
+                        pre_marker = Some(line);
+                        state = ParserState::CentralBlock;
+                    } else if post_sentinel.is_match(&line) {
+                        post_marker = Some(line);
+                        state = ParserState::PostBlock;
+                    } else {
+                        pre_lines.push(line);
+                    }
+                }
+                ParserState::CentralBlock => {
+                    if post_sentinel.is_match(&line) {
+                        post_marker = Some(line);
+                        state = ParserState::PostBlock;
+                    } else {
+                        central_lines.push(line);
+                    }
+                }
+                ParserState::PostBlock | ParserState::Finished => post_lines.push(line),
+            }
+        }
+
+        let pre_content = apply_transform(self.pre_transform.as_deref(), &pre_lines.join("\n"))?;
+        let central_content =
+            apply_transform(self.central_transform.as_deref(), &central_lines.join("\n"))?;
+        let post_content =
+            apply_transform(self.post_transform.as_deref(), &post_lines.join("\n"))?;
+
+        let mut rewritten = String::new();
+        push_block(&mut rewritten, &pre_content);
+        if let Some(marker) = &pre_marker {
+            rewritten.push_str(marker);
+            rewritten.push('\n');
+        }
+        push_block(&mut rewritten, &central_content);
+        if let Some(marker) = &post_marker {
+            rewritten.push_str(marker);
+            rewritten.push('\n');
+        }
+        push_block(&mut rewritten, &post_content);
+
+        let destination = output.as_ref().map(|o| o.as_ref());
+        match destination {
+            Some(destination) => {
+                std::fs::write(destination, &rewritten).map_err(|source| ProcessError::Write {
+                    path: destination.to_path_buf(),
+                    source,
+                })
+            }
+            None => write_atomically(path, &rewritten).map_err(|source| ProcessError::Write {
+                path: path.to_path_buf(),
+                source,
+            }),
+        }
+    }
+
+    /// Processes `path` once, then watches it for further modifications and
+    /// re-runs the full parse on each change.
+    ///
+    /// The path is resolved to its canonical, absolute form up front, and the
+    /// *containing directory* (rather than the file itself) is what's
+    /// actually watched, filtering events down to this file's name. Editors
+    /// that save by writing a temp file and renaming over the target (vim,
+    /// VS Code, ...) replace the inode; a watch registered on the file
+    /// directly would silently stop firing after the first such save, while
+    /// a directory watch keeps seeing the rename. A burst of rapid writes is
+    /// coalesced by a debounce window into a single re-process, and a short
+    /// banner is printed before each re-run so streamed output stays
+    /// distinguishable between runs. Each re-run builds a brand-new
+    /// `FileParser`, so no block state survives from one run to the next.
+    fn watch_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+        pre_sentinel: impl Into<Sentinel> + Clone,
+        post_sentinel: impl Into<Sentinel> + Clone,
+    ) -> Result<(), ProcessError> {
+        let path = std::fs::canonicalize(path.as_ref()).map_err(|source| ProcessError::Open {
+            path: path.as_ref().to_path_buf(),
+            source,
+        })?;
+        let dir = path.parent().map(Path::to_path_buf).ok_or_else(|| {
+            ProcessError::Open {
+                path: path.clone(),
+                source: io::Error::new(io::ErrorKind::InvalidInput, "path has no parent directory"),
+            }
+        })?;
+        let file_name = path.file_name().map(|name| name.to_os_string());
+
+        const DEBOUNCE: Duration = Duration::from_millis(200);
+
+        self.process_file(&path, pre_sentinel.clone(), post_sentinel.clone())?;
+
+        let (tx, rx) = channel::<notify::Result<notify::Event>>();
+        let mut watcher =
+            notify::recommended_watcher(tx).map_err(|err| ProcessError::Handler(Box::new(err)))?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(|err| ProcessError::Handler(Box::new(err)))?;
+
+        while let Ok(message) = rx.recv() {
+            let relevant =
+                matches!(message, Ok(event) if event_touches_file(&event, file_name.as_deref()));
+            if !relevant {
+                continue;
+            }
+
+            // Drain anything else that arrives within the debounce window so
+            // a flurry of writes (or a temp-file-then-rename save) collapses
+            // into a single re-process.
+            while let Ok(message) = rx.recv_timeout(DEBOUNCE) {
+                let _ = message;
+            }
+
+            println!("--- re-processing {} ---", path.display());
+            self.process_file(&path, pre_sentinel.clone(), post_sentinel.clone())?;
+        }
+
+        Ok(())
+    }
 }
+
+/// Whether a filesystem event touches a file named `file_name`, regardless
+/// of which path under the watched directory it fired on — used to filter a
+/// directory watch down to a single file of interest, including the rename
+/// that lands when an editor saves via temp-file-then-rename.
+fn event_touches_file(event: &notify::Event, file_name: Option<&std::ffi::OsStr>) -> bool {
+    file_name.is_some_and(|name| event.paths.iter().any(|p| p.file_name() == Some(name)))
+}
+
+fn apply_transform(
+    transform: Option<&dyn BlockTransform>,
+    content: &str,
+) -> Result<String, ProcessError> {
+    match transform {
+        Some(transform) => transform
+            .transform(content)
+            .map(|rewritten| rewritten.unwrap_or_else(|| content.to_string()))
+            .map_err(ProcessError::Handler),
+        None => Ok(content.to_string()),
+    }
+}
+
+fn push_block(output: &mut String, content: &str) {
+    if content.is_empty() {
+        return;
+    }
+    output.push_str(content);
+    output.push('\n');
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(err) = run(args) {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+}
+
+fn run(mut args: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+    if args.is_empty() {
+        return run_default();
+    }
+
+    let command = args.remove(0);
+    match command.as_str() {
+        "batch" => run_batch(args),
+        "rewrite" => run_rewrite(args),
+        "columns" => run_columns(args),
+        "watch" => run_watch(args),
+        other => Err(format!(
+            "unknown subcommand: {other} (expected 'batch', 'rewrite', 'columns', 'watch')"
+        )
+        .into()),
+    }
+}
+
+fn run_default() -> Result<(), Box<dyn std::error::Error>> {
+    let processor = FileProcessor::new();
+    processor.process_file("example.txt", "--- PRE ---", "--- POST ---")?;
+    Ok(())
+}
+
+/// `batch <root>... [--include glob]... [--exclude glob]... [--pre text]...
+/// [--pre-regex pattern]... [--post text]... [--post-regex pattern]...`
+///
+/// Recursively collects files under one or more roots and processes them
+/// concurrently via `FileProcessor::process_paths`.
+fn run_batch(mut args: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+    let includes = take_flag_values(&mut args, "--include");
+    let excludes = take_flag_values(&mut args, "--exclude");
+    let (pre, post) = take_sentinels(&mut args, "--- PRE ---", "--- POST ---")?;
+
+    if args.is_empty() {
+        return Err("batch: expected at least one root path".into());
+    }
+
     let processor = FileProcessor::new();
+    let failures = processor.process_paths(&args, &includes, &excludes, pre, post)?;
+    if failures > 0 {
+        return Err(format!("{failures} file(s) failed to process").into());
+    }
+    Ok(())
+}
+
+/// `rewrite <path> [--output path] [--uppercase-pre] [--uppercase-central]
+/// [--uppercase-post] [--pre text]... [--pre-regex pattern]... [--post text]...
+/// [--post-regex pattern]...`
+///
+/// Reassembles `path`'s pre/central/post blocks via `FileProcessor::rewrite_file`,
+/// writing the result back over `path` atomically, or to `--output` when given.
+fn run_rewrite(mut args: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+    let output = take_flag_value(&mut args, "--output");
+    let uppercase_pre = take_flag(&mut args, "--uppercase-pre");
+    let uppercase_central = take_flag(&mut args, "--uppercase-central");
+    let uppercase_post = take_flag(&mut args, "--uppercase-post");
+    let (pre, post) = take_sentinels(&mut args, "--- PRE ---", "--- POST ---")?;
+
+    let path = if args.is_empty() {
+        return Err("rewrite: expected a file path".into());
+    } else {
+        args.remove(0)
+    };
+
+    let mut processor = FileProcessor::new();
+    if uppercase_pre {
+        processor = processor.with_pre_transform(UppercaseTransform);
+    }
+    if uppercase_central {
+        processor = processor.with_central_transform(UppercaseTransform);
+    }
+    if uppercase_post {
+        processor = processor.with_post_transform(UppercaseTransform);
+    }
+
+    processor.rewrite_file(path, output, pre, post)?;
+    Ok(())
+}
 
-    processor
-        .process_file("example.txt", "--- PRE ---", "--- POST ---")
-        .unwrap();
+/// `columns <path> [--delimiter c] [--select 1,3] [--sort 2n,1]
+/// [--pre text]... [--pre-regex pattern]... [--post text]... [--post-regex pattern]...`
+///
+/// Processes `path` with a `ColumnHandler` installed as the central-block
+/// handler, selecting/reordering and sorting the delimited rows found there.
+fn run_columns(mut args: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+    let delimiter = take_flag_value(&mut args, "--delimiter")
+        .map(|value| value.chars().next().ok_or("--delimiter: expected a character"))
+        .transpose()?
+        .unwrap_or('\t');
+    let select = take_flag_value(&mut args, "--select");
+    let sort = take_flag_value(&mut args, "--sort");
+    let (pre, post) = take_sentinels(&mut args, "--- PRE ---", "--- POST ---")?;
+
+    let path = if args.is_empty() {
+        return Err("columns: expected a file path".into());
+    } else {
+        args.remove(0)
+    };
+
+    let mut handler = ColumnHandler::new().with_delimiter(delimiter);
+    if let Some(select) = select {
+        let columns: Vec<usize> = select
+            .split(',')
+            .map(parse_one_indexed_column)
+            .collect::<Result<_, _>>()?;
+        handler = handler.with_columns(columns);
+    }
+    if let Some(sort) = sort {
+        handler = handler.with_sort(&sort);
+    }
+
+    let processor = FileProcessor::new().with_central_handler(handler);
+    processor.process_file(path, pre, post)?;
+    Ok(())
+}
+
+/// `watch <path> [--pre text]... [--pre-regex pattern]... [--post text]...
+/// [--post-regex pattern]...`
+///
+/// Processes `path` once, then re-processes it on every subsequent change
+/// via `FileProcessor::watch_file`.
+fn run_watch(mut args: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+    let (pre, post) = take_sentinels(&mut args, "--- PRE ---", "--- POST ---")?;
+
+    let path = if args.is_empty() {
+        return Err("watch: expected a file path".into());
+    } else {
+        args.remove(0)
+    };
+
+    let processor = FileProcessor::new();
+    processor.watch_file(path, pre, post)?;
+    Ok(())
+}
+
+/// Parses one `--select` part as a 1-indexed column number (matching
+/// `CompareKey::parse`'s `--sort` convention), rejecting `0` rather than
+/// silently clamping it to the first column.
+fn parse_one_indexed_column(part: &str) -> Result<usize, Box<dyn std::error::Error>> {
+    let column: usize = part.trim().parse()?;
+    if column == 0 {
+        return Err("--select: column numbers are 1-indexed; 0 is not valid".into());
+    }
+    Ok(column - 1)
+}
+
+/// Removes the first occurrence of a bare boolean `flag` from `args`,
+/// returning whether it was present.
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    match args.iter().position(|arg| arg == flag) {
+        Some(index) => {
+            args.remove(index);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Pulls the value following the first occurrence of `flag` out of `args`,
+/// removing both entries. Returns `None` if the flag isn't present.
+fn take_flag_value(args: &mut Vec<String>, flag: &str) -> Option<String> {
+    let index = args.iter().position(|arg| arg == flag)?;
+    args.remove(index);
+    if index < args.len() {
+        Some(args.remove(index))
+    } else {
+        None
+    }
+}
+
+/// Repeatedly applies `take_flag_value`, collecting every value given for a
+/// repeatable flag like `--include` in the order it was passed.
+fn take_flag_values(args: &mut Vec<String>, flag: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    while let Some(value) = take_flag_value(args, flag) {
+        values.push(value);
+    }
+    values
+}
+
+/// Builds the pre/post sentinels for a subcommand from repeatable
+/// `--pre`/`--pre-regex` and `--post`/`--post-regex` flags, combining more
+/// than one pattern per side into `Sentinel::AnyOf`. Falls back to the given
+/// defaults when no flags for that side were passed.
+fn take_sentinels(
+    args: &mut Vec<String>,
+    default_pre: &str,
+    default_post: &str,
+) -> Result<(Sentinel, Sentinel), Box<dyn std::error::Error>> {
+    let pre_exact = take_flag_values(args, "--pre");
+    let pre_regex = take_flag_values(args, "--pre-regex");
+    let post_exact = take_flag_values(args, "--post");
+    let post_regex = take_flag_values(args, "--post-regex");
+
+    let pre = from_specs(&pre_exact, &pre_regex)?.unwrap_or_else(|| default_pre.into());
+    let post = from_specs(&post_exact, &post_regex)?.unwrap_or_else(|| default_post.into());
+
+    Ok((pre, post))
 }
 
 #[cfg(test)]
@@ -227,15 +778,18 @@ mod tests {
             let reader = std::io::BufReader::new(file);
 
             let processor = FileProcessor {
-                pre_handler: Box::new(TestHandler::new("PRE-BLOCK", self.captured_output.clone())),
-                central_handler: Some(Box::new(TestHandler::new(
+                pre_handler: Arc::new(TestHandler::new("PRE-BLOCK", self.captured_output.clone())),
+                central_handler: Some(Arc::new(TestHandler::new(
                     "CENTRAL-BLOCK",
                     self.captured_output.clone(),
                 ))),
-                post_handler: Some(Box::new(TestHandler::new(
+                post_handler: Some(Arc::new(TestHandler::new(
                     "POST-BLOCK",
                     self.captured_output.clone(),
                 ))),
+                pre_transform: None,
+                central_transform: None,
+                post_transform: None,
             };
 
             let mut parser = FileParser::new(pre_sentinel, post_sentinel);
@@ -307,4 +861,154 @@ mod tests {
         assert!(!output[0].contains("===  End: CENTRAL-BLOCK  ==="));
         assert!(!output[0].contains("=== Start: POST-BLOCK ==="));
     }
+
+    struct BrokenPipeHandler;
+
+    impl BlockHandler for BrokenPipeHandler {
+        fn handle(&self, _content: &str) -> Result<(), Box<dyn std::error::Error>> {
+            Err(Box::new(std::io::Error::from(
+                std::io::ErrorKind::BrokenPipe,
+            )))
+        }
+    }
+
+    #[test]
+    fn test_broken_pipe_marks_parser_finished_and_stops_further_handler_calls() {
+        let captured = Arc::new(Mutex::new(Vec::new()));
+        let processor = FileProcessor {
+            pre_handler: Arc::new(BrokenPipeHandler),
+            central_handler: Some(Arc::new(TestHandler::new("CENTRAL-BLOCK", captured.clone()))),
+            post_handler: Some(Arc::new(TestHandler::new("POST-BLOCK", captured.clone()))),
+            pre_transform: None,
+            central_transform: None,
+            post_transform: None,
+        };
+
+        let mut parser = FileParser::new("*pre*", "*post*");
+
+        parser.process_line("*pre*", &processor).unwrap();
+        assert!(parser.is_finished());
+
+        // Once finished, further lines and a final `finish()` must not
+        // re-invoke any handler.
+        parser.process_line("more content", &processor).unwrap();
+        parser.finish(&processor).unwrap();
+
+        assert!(captured.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn rewrite_file_applies_central_transform_and_preserves_sentinels() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "pre line").unwrap();
+        writeln!(file, "*pre*").unwrap();
+        writeln!(file, "central line").unwrap();
+        writeln!(file, "*post*").unwrap();
+        writeln!(file, "post line").unwrap();
+
+        let processor = FileProcessor::new().with_central_transform(UppercaseTransform);
+        processor
+            .rewrite_file(file.path(), None::<&std::path::Path>, "*pre*", "*post*")
+            .unwrap();
+
+        let rewritten = std::fs::read_to_string(file.path()).unwrap();
+        assert!(rewritten.contains("pre line"));
+        assert!(rewritten.contains("*pre*"));
+        assert!(rewritten.contains("CENTRAL LINE"));
+        assert!(!rewritten.contains("central line"));
+        assert!(rewritten.contains("*post*"));
+        assert!(rewritten.contains("post line"));
+    }
+
+    #[test]
+    fn rewrite_file_applies_pre_and_post_transforms_when_requested() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "pre line").unwrap();
+        writeln!(file, "*pre*").unwrap();
+        writeln!(file, "central line").unwrap();
+        writeln!(file, "*post*").unwrap();
+        writeln!(file, "post line").unwrap();
+
+        let processor = FileProcessor::new()
+            .with_pre_transform(UppercaseTransform)
+            .with_post_transform(UppercaseTransform);
+        processor
+            .rewrite_file(file.path(), None::<&std::path::Path>, "*pre*", "*post*")
+            .unwrap();
+
+        let rewritten = std::fs::read_to_string(file.path()).unwrap();
+        assert!(rewritten.contains("PRE LINE"));
+        assert!(!rewritten.contains("pre line"));
+        assert!(rewritten.contains("central line"));
+        assert!(rewritten.contains("POST LINE"));
+        assert!(!rewritten.contains("post line"));
+    }
+
+    #[test]
+    fn rewrite_file_refuses_in_place_rewrite_of_gzip_input() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+
+        let mut file = tempfile::Builder::new().suffix(".gz").tempfile().unwrap();
+        {
+            let mut encoder = GzEncoder::new(&mut file, Compression::default());
+            writeln!(encoder, "pre line").unwrap();
+            writeln!(encoder, "*pre*").unwrap();
+            writeln!(encoder, "central line").unwrap();
+            writeln!(encoder, "*post*").unwrap();
+            writeln!(encoder, "post line").unwrap();
+            encoder.finish().unwrap();
+        }
+
+        let processor = FileProcessor::new();
+        let result = processor.rewrite_file(file.path(), None::<&std::path::Path>, "*pre*", "*post*");
+
+        assert!(result.is_err());
+        // The file on disk must still be valid gzip, not silently replaced
+        // with plain text.
+        assert!(input::is_compressed(file.path()).unwrap());
+    }
+
+    #[test]
+    fn parse_one_indexed_column_converts_to_zero_indexed() {
+        assert_eq!(parse_one_indexed_column("1").unwrap(), 0);
+        assert_eq!(parse_one_indexed_column(" 3 ").unwrap(), 2);
+    }
+
+    #[test]
+    fn parse_one_indexed_column_rejects_zero_and_non_numeric() {
+        assert!(parse_one_indexed_column("0").is_err());
+        assert!(parse_one_indexed_column("abc").is_err());
+    }
+
+    #[test]
+    fn event_touches_file_matches_by_name_regardless_of_directory() {
+        let event = notify::Event::new(notify::EventKind::Modify(notify::event::ModifyKind::Any))
+            .add_path(std::path::PathBuf::from("/some/dir/target.txt"));
+
+        assert!(event_touches_file(
+            &event,
+            Some(std::ffi::OsStr::new("target.txt"))
+        ));
+        assert!(!event_touches_file(
+            &event,
+            Some(std::ffi::OsStr::new("other.txt"))
+        ));
+        assert!(!event_touches_file(&event, None));
+    }
+
+    #[test]
+    fn event_touches_file_matches_the_rename_that_lands_on_a_save() {
+        // Editors that save via temp-file-then-rename fire an event whose
+        // path is the final, renamed-to name — this must still match.
+        let event = notify::Event::new(notify::EventKind::Modify(notify::event::ModifyKind::Name(
+            notify::event::RenameMode::To,
+        )))
+        .add_path(std::path::PathBuf::from("/some/dir/target.txt"));
+
+        assert!(event_touches_file(
+            &event,
+            Some(std::ffi::OsStr::new("target.txt"))
+        ));
+    }
 }
@@ -0,0 +1,116 @@
+use regex::Regex;
+
+/// A matcher used to recognise the sentinel lines that delimit blocks.
+///
+/// `Exact` reproduces the original trimmed string-equality check, `Regex`
+/// matches the raw (untrimmed) line against a compiled pattern so sentinels
+/// can tolerate variable whitespace or decoration (e.g. `^-{3,}\s*PRE\s*-{3,}$`),
+/// and `AnyOf` lets several sentinel styles be recognised in a single pass.
+#[derive(Clone)]
+pub enum Sentinel {
+    Exact(String),
+    Regex(Regex),
+    AnyOf(Vec<Sentinel>),
+}
+
+impl Sentinel {
+    pub fn is_match(&self, line: &str) -> bool {
+        match self {
+            Sentinel::Exact(expected) => line.trim() == expected,
+            Sentinel::Regex(re) => re.is_match(line),
+            Sentinel::AnyOf(sentinels) => sentinels.iter().any(|s| s.is_match(line)),
+        }
+    }
+}
+
+impl From<&str> for Sentinel {
+    fn from(exact: &str) -> Self {
+        Sentinel::Exact(exact.to_string())
+    }
+}
+
+impl From<String> for Sentinel {
+    fn from(exact: String) -> Self {
+        Sentinel::Exact(exact)
+    }
+}
+
+impl From<Regex> for Sentinel {
+    fn from(re: Regex) -> Self {
+        Sentinel::Regex(re)
+    }
+}
+
+/// Builds a `Sentinel` from explicit exact strings and regex pattern
+/// strings, combining more than one into `AnyOf` so a block boundary can be
+/// recognised by any of several marker styles in a single pass. Returns
+/// `None` when both lists are empty, leaving the default sentinel choice to
+/// the caller.
+pub fn from_specs(exact: &[String], regex: &[String]) -> Result<Option<Sentinel>, regex::Error> {
+    let mut sentinels = Vec::new();
+    for pattern in regex {
+        sentinels.push(Sentinel::Regex(Regex::new(pattern)?));
+    }
+    sentinels.extend(exact.iter().cloned().map(Sentinel::Exact));
+
+    Ok(match sentinels.len() {
+        0 => None,
+        1 => Some(sentinels.remove(0)),
+        _ => Some(Sentinel::AnyOf(sentinels)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_matches_trimmed_line() {
+        let sentinel = Sentinel::Exact("*pre*".to_string());
+        assert!(sentinel.is_match("  *pre*  "));
+        assert!(!sentinel.is_match("*post*"));
+    }
+
+    #[test]
+    fn regex_matches_raw_line() {
+        let sentinel = Sentinel::Regex(Regex::new(r"^-{3,}\s*PRE\s*-{3,}$").unwrap());
+        assert!(sentinel.is_match("--- PRE ---"));
+        assert!(sentinel.is_match("----PRE----"));
+        assert!(!sentinel.is_match("PRE"));
+    }
+
+    #[test]
+    fn any_of_matches_if_any_branch_matches() {
+        let sentinel = Sentinel::AnyOf(vec![
+            Sentinel::Exact("*pre*".to_string()),
+            Sentinel::Regex(Regex::new(r"^-{3,}\s*PRE\s*-{3,}$").unwrap()),
+        ]);
+        assert!(sentinel.is_match("*pre*"));
+        assert!(sentinel.is_match("--- PRE ---"));
+        assert!(!sentinel.is_match("*post*"));
+    }
+
+    #[test]
+    fn from_specs_combines_multiple_styles_into_any_of() {
+        let sentinel = from_specs(
+            &["*pre*".to_string()],
+            &[r"^-{3,}\s*PRE\s*-{3,}$".to_string()],
+        )
+        .unwrap()
+        .unwrap();
+
+        assert!(matches!(sentinel, Sentinel::AnyOf(_)));
+        assert!(sentinel.is_match("*pre*"));
+        assert!(sentinel.is_match("--- PRE ---"));
+    }
+
+    #[test]
+    fn from_specs_returns_none_when_empty() {
+        assert!(from_specs(&[], &[]).unwrap().is_none());
+    }
+
+    #[test]
+    fn from_specs_rejects_invalid_regex() {
+        assert!(from_specs(&[], &["(".to_string()]).is_err());
+    }
+}
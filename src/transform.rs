@@ -0,0 +1,78 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Companion to `BlockHandler` for handlers that rewrite a block's content
+/// rather than merely observing it.
+///
+/// Returning `Some(new)` replaces the block's content; `None` leaves the
+/// original content untouched.
+pub trait BlockTransform: Send + Sync {
+    fn transform(&self, content: &str) -> Result<Option<String>, Box<dyn std::error::Error>>;
+}
+
+/// Writes `contents` to `path` atomically via a temp file in the same
+/// directory followed by a rename, so readers never observe a partially
+/// written file.
+pub fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    let temp_path = temp_path_for(path);
+
+    fs::write(&temp_path, contents)?;
+    fs::rename(&temp_path, path)?;
+
+    Ok(())
+}
+
+fn temp_path_for(target: &Path) -> PathBuf {
+    let dir = target.parent().unwrap_or_else(|| Path::new("."));
+    let file_name = target
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    dir.join(format!(".{file_name}.{}.tmp", std::process::id()))
+}
+
+/// A `BlockTransform` that upper-cases a block's content, leaving empty
+/// blocks untouched.
+pub struct UppercaseTransform;
+
+impl BlockTransform for UppercaseTransform {
+    fn transform(&self, content: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        if content.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(content.to_uppercase()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uppercase_transform_upper_cases_non_empty_content() {
+        let transform = UppercaseTransform;
+        assert_eq!(
+            transform.transform("Hello, World!").unwrap(),
+            Some("HELLO, WORLD!".to_string())
+        );
+    }
+
+    #[test]
+    fn uppercase_transform_leaves_empty_content_untouched() {
+        let transform = UppercaseTransform;
+        assert_eq!(transform.transform("").unwrap(), None);
+    }
+
+    #[test]
+    fn write_atomically_replaces_existing_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("target.txt");
+        fs::write(&path, "original").unwrap();
+
+        write_atomically(&path, "replaced").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "replaced");
+    }
+}